@@ -0,0 +1,14 @@
+use crate::context::*;
+use crate::settings;
+
+/// Route an editor command (as sent by the `.kak` `define-command`s in
+/// `rc/`) to its Rust handler. Most editor commands actually flow through
+/// the `textDocument/*` request path instead and never reach here; this
+/// covers the handful, like `lsp-show-config`, that are pure client-side
+/// rage/debug commands with no corresponding LSP request.
+pub fn dispatch_editor_command(meta: &EditorMeta, ctx: &mut Context, command: &str) {
+    match command {
+        "show-config" => settings::show_config(meta, ctx),
+        _ => {}
+    }
+}
@@ -0,0 +1,78 @@
+//! Per-feature request routing across the servers attached to a buffer.
+//!
+//! Each function here is the dispatch entry point for one LSP capability:
+//! it narrows the attached servers down to the ones configured for that
+//! feature via `settings::servers_for_feature`, then fans the request out
+//! to just those. `publish_diagnostics` is the exception: it handles one
+//! server's notification and checks that server alone with
+//! `settings::has_feature`.
+
+use crate::context::*;
+use crate::settings::{has_feature, servers_for_feature};
+use crate::types::*;
+use lsp_types::*;
+
+pub fn hover(meta: &EditorMeta, ctx: &mut Context, servers: &[ServerId], params: HoverParams) {
+    for server_id in servers_for_feature(servers, meta, ctx, "hover") {
+        ctx.call::<HoverRequest>(meta.clone(), server_id, params.clone());
+    }
+}
+
+pub fn completion(
+    meta: &EditorMeta,
+    ctx: &mut Context,
+    servers: &[ServerId],
+    params: CompletionParams,
+) {
+    for server_id in servers_for_feature(servers, meta, ctx, "completion") {
+        ctx.call::<CompletionRequest>(meta.clone(), server_id, params.clone());
+    }
+}
+
+pub fn definition(
+    meta: &EditorMeta,
+    ctx: &mut Context,
+    servers: &[ServerId],
+    params: GotoDefinitionParams,
+) {
+    for server_id in servers_for_feature(servers, meta, ctx, "definition") {
+        ctx.call::<GotoDefinition>(meta.clone(), server_id, params.clone());
+    }
+}
+
+pub fn formatting(
+    meta: &EditorMeta,
+    ctx: &mut Context,
+    servers: &[ServerId],
+    params: DocumentFormattingParams,
+) {
+    for server_id in servers_for_feature(servers, meta, ctx, "formatting") {
+        ctx.call::<Formatting>(meta.clone(), server_id, params.clone());
+    }
+}
+
+/// A server pushed us `textDocument/publishDiagnostics`; only forward it to
+/// Kakoune if that server has the `diagnostics` feature enabled, so a server
+/// excluded from diagnostics (e.g. a formatter-only secondary server) can't
+/// clutter the buffer with noise a linter is already reporting.
+pub fn publish_diagnostics(
+    meta: &EditorMeta,
+    ctx: &mut Context,
+    server_id: ServerId,
+    params: PublishDiagnosticsParams,
+) {
+    if has_feature(meta, ctx, server_id, "diagnostics") {
+        ctx.publish_diagnostics(meta.clone(), server_id, params);
+    }
+}
+
+pub fn code_action(
+    meta: &EditorMeta,
+    ctx: &mut Context,
+    servers: &[ServerId],
+    params: CodeActionParams,
+) {
+    for server_id in servers_for_feature(servers, meta, ctx, "code_action") {
+        ctx.call::<CodeActionRequest>(meta.clone(), server_id, params.clone());
+    }
+}
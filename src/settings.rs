@@ -5,6 +5,8 @@ use crate::context::*;
 use crate::controller::ParserState;
 use crate::types::*;
 use crate::util::*;
+use lsp_types::notification::{DidChangeConfiguration, Notification};
+use lsp_types::DidChangeConfigurationParams;
 use serde_json::Value;
 
 fn request_dynamic_configuration_from_kakoune(meta: &EditorMeta, ctx: &mut Context) -> Option<()> {
@@ -24,6 +26,21 @@ pub fn request_initialization_options_from_kakoune(
     ctx: &mut Context,
 ) -> Vec<Option<Value>> {
     request_dynamic_configuration_from_kakoune(meta, ctx);
+    resolve_initialization_options(servers, meta, ctx)
+}
+
+/// Same resolution as [`request_initialization_options_from_kakoune`], but
+/// assumes `ctx.dynamic_config` is already current and does not re-fetch
+/// `lsp_config` from Kakoune. Callers that run while already reacting to a
+/// freshly-received `lsp_config` (e.g. `reconcile_server_settings`) must use
+/// this instead of `request_initialization_options_from_kakoune`, whose
+/// re-fetch calls back into `record_dynamic_config` and would recurse
+/// forever.
+fn resolve_initialization_options(
+    servers: &[ServerId],
+    meta: &EditorMeta,
+    ctx: &mut Context,
+) -> Vec<Option<Value>> {
     let mut sections = Vec::with_capacity(servers.len());
     for &server_id in servers {
         let server_name = &ctx.server(server_id).name;
@@ -67,6 +84,210 @@ pub fn configured_section(
     })
 }
 
+/// Does `server_id`'s `features` configuration allow routing `feature` to it?
+///
+/// A server with an empty `only` set participates in every feature unless
+/// explicitly `excluded`; a non-empty `only` set restricts it to just those
+/// features (still subject to `excluded`, so the two can't be used to
+/// contradict each other).
+pub fn has_feature(meta: &EditorMeta, ctx: &Context, server_id: ServerId, feature: &str) -> bool {
+    let server_name = &ctx.server(server_id).name;
+    let features = server_configs(&ctx.config, meta)
+        .get(server_name)
+        .map(|cfg| &cfg.features);
+    match features {
+        Some(features) => {
+            (features.only.is_empty() || features.only.contains(feature))
+                && !features.excluded.contains(feature)
+        }
+        None => true,
+    }
+}
+
+/// Narrow `servers` down to the ones that have `feature` enabled, per
+/// [`has_feature`]. Dispatch sites in `language_features` that route one LSP
+/// request across every server attached to a buffer (hover, completion,
+/// definition, formatting, code_action, ...) filter through this first, so
+/// two servers configured for the same language don't both answer the same
+/// request.
+pub fn servers_for_feature(
+    servers: &[ServerId],
+    meta: &EditorMeta,
+    ctx: &Context,
+    feature: &str,
+) -> Vec<ServerId> {
+    servers
+        .iter()
+        .copied()
+        .filter(|&server_id| has_feature(meta, ctx, server_id, feature))
+        .collect()
+}
+
+/// Where a leaf setting value came from, in increasing precedence order:
+/// static base < project/dynamic < legacy override. Only `lsp-show-config`
+/// (via [`resolve_layered_settings`]) deep-merges the layers in this order.
+/// The settings actually sent to a server come from
+/// `resolve_initialization_options`, which does not merge: it takes the
+/// first layer present in the order dynamic, legacy, static. The two can
+/// therefore disagree on which layer wins.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConfigLayer {
+    /// `server_config.settings` in the static `kak-lsp.toml`.
+    Static,
+    /// `language_server.<name>.settings` supplied via the project's `lsp_config`.
+    Dynamic,
+    /// `lsp_server_initialization_options` set from Kakoune.
+    Legacy,
+}
+
+impl ConfigLayer {
+    fn label(self) -> &'static str {
+        match self {
+            ConfigLayer::Static => "static server_config.settings",
+            ConfigLayer::Dynamic => "project lsp_config",
+            ConfigLayer::Legacy => "legacy lsp_server_initialization_options",
+        }
+    }
+}
+
+/// Deep-merge `overlay` into `base`, overwriting scalar leaves and recording
+/// which `layer` last wrote each leaf into the identically-shaped `provenance`
+/// tree. Reuses the same recursive-descent shape as [`insert_value`].
+fn merge_layer(base: &mut Value, provenance: &mut Value, overlay: &Value, layer: ConfigLayer) {
+    match overlay {
+        Value::Object(overlay_map) => {
+            if !base.is_object() {
+                *base = Value::Object(serde_json::Map::new());
+            }
+            if !provenance.is_object() {
+                *provenance = Value::Object(serde_json::Map::new());
+            }
+            for (key, overlay_value) in overlay_map {
+                let base_entry = base
+                    .as_object_mut()
+                    .unwrap()
+                    .entry(key.clone())
+                    .or_insert(Value::Null);
+                let provenance_entry = provenance
+                    .as_object_mut()
+                    .unwrap()
+                    .entry(key.clone())
+                    .or_insert(Value::Null);
+                merge_layer(base_entry, provenance_entry, overlay_value, layer);
+            }
+        }
+        leaf => {
+            *base = leaf.clone();
+            *provenance = Value::String(layer.label().into());
+        }
+    }
+}
+
+/// Resolve `server_id`'s settings by deep-merging all layers in precedence
+/// order (static base < project/dynamic < legacy override), per
+/// [`ConfigLayer`]. Returns the merged settings alongside a same-shaped tree
+/// recording which layer contributed each leaf, for `lsp-show-config`. This
+/// merge is not what `resolve_initialization_options` sends to the server.
+pub fn resolve_layered_settings(
+    meta: &EditorMeta,
+    ctx: &Context,
+    server_id: ServerId,
+    dynamic_settings: Option<&Value>,
+    legacy_settings: Option<&Value>,
+) -> (Value, Value) {
+    let server_name = &ctx.server(server_id).name;
+    let server_config = server_configs(&ctx.config, meta).get(server_name).unwrap();
+
+    let mut settings = Value::Object(serde_json::Map::new());
+    let mut provenance = Value::Object(serde_json::Map::new());
+
+    if let Some(static_settings) =
+        configured_section(meta, ctx, server_id, server_config.settings.as_ref())
+    {
+        merge_layer(&mut settings, &mut provenance, &static_settings, ConfigLayer::Static);
+    }
+    if let Some(dynamic_settings) = dynamic_settings {
+        merge_layer(&mut settings, &mut provenance, dynamic_settings, ConfigLayer::Dynamic);
+    }
+    if let Some(legacy_settings) = legacy_settings {
+        merge_layer(&mut settings, &mut provenance, legacy_settings, ConfigLayer::Legacy);
+    }
+
+    (settings, provenance)
+}
+
+/// Handle the `lsp-show-config` rage command: print each attached server's
+/// layered-merge settings (see [`resolve_layered_settings`]) plus which
+/// config layer contributed each value.
+///
+/// Registered in `controller::dispatch_editor_command` against the
+/// `lsp-show-config` editor command (see `rc/lsp-show-config.kak` for the
+/// `define-command`); the report is handed back to Kakoune via the
+/// `lsp-show-config-output` command rather than `lsp-show-config` itself, so
+/// the request-triggering command and its response handler don't collide.
+pub fn show_config(meta: &EditorMeta, ctx: &mut Context) {
+    request_dynamic_configuration_from_kakoune(meta, ctx);
+
+    let mut report = String::new();
+    for (server_name, server) in &meta.language_server {
+        let server_id = *ctx
+            .route_cache
+            .get(&(server_name.clone(), server.root.clone()))
+            .unwrap();
+        let dynamic_settings = ctx
+            .dynamic_config
+            .language_server
+            .get(server_name)
+            .and_then(|v| v.settings.clone());
+        let dynamic_settings = configured_section(meta, ctx, server_id, dynamic_settings.as_ref());
+        let legacy_settings = request_legacy_initialization_options_from_kakoune(meta, ctx);
+        let (settings, provenance) = resolve_layered_settings(
+            meta,
+            ctx,
+            server_id,
+            dynamic_settings.as_ref(),
+            legacy_settings.as_ref(),
+        );
+        report.push_str(&format!(
+            "* {}\n\nsettings:\n{}\n\nprovenance:\n{}\n\n",
+            server_name,
+            serde_json::to_string_pretty(&settings).unwrap(),
+            serde_json::to_string_pretty(&provenance).unwrap(),
+        ));
+    }
+
+    ctx.exec(
+        meta.clone(),
+        format!("lsp-show-config-output {}", editor_quote(&report)),
+    );
+}
+
+/// Should `server_id` actually be spawned right now?
+///
+/// A server configured with `requires_configuration = true` stays dormant
+/// until a matching `settings_section` shows up along the static, dynamic,
+/// or legacy path, so it doesn't start up just to immediately be useless in
+/// projects that haven't opted in. It auto-activates the moment a project's
+/// `lsp_config` starts supplying it settings. Consulted from
+/// `record_dynamic_config` on every `lsp_config` refresh, so a server that
+/// just became configured gets reconciled (and thus started) without
+/// waiting for some unrelated trigger.
+pub fn should_start_server(meta: &EditorMeta, ctx: &mut Context, server_id: ServerId) -> bool {
+    let server_name = &ctx.server(server_id).name;
+    let server_config = server_configs(&ctx.config, meta).get(server_name).unwrap();
+    if !server_config.requires_configuration {
+        return true;
+    }
+    // `resolve_initialization_options`, not `request_initialization_options_from_kakoune`:
+    // this runs from within `record_dynamic_config`'s reaction to a fresh
+    // `lsp_config`, so `ctx.dynamic_config` is already current, and
+    // re-fetching it here would recurse back into `record_dynamic_config`.
+    resolve_initialization_options(&[server_id], meta, ctx)
+        .pop()
+        .flatten()
+        .is_some()
+}
+
 pub fn record_dynamic_config(meta: &EditorMeta, ctx: &mut Context, config: &str) {
     debug!(meta.session, "lsp_config:\n{}", config);
     match toml::from_str(config) {
@@ -83,14 +304,88 @@ pub fn record_dynamic_config(meta: &EditorMeta, ctx: &mut Context, config: &str)
         }
     };
     if !is_using_legacy_toml(&ctx.config) {
-        for (server_name, server) in &meta.language_server {
-            let server_id = ctx
-                .route_cache
-                .get(&(server_name.clone(), server.root.clone()))
-                .unwrap();
-            ctx.language_servers.get_mut(server_id).unwrap().settings = server.settings.clone();
+        let server_ids: Vec<ServerId> = meta
+            .language_server
+            .iter()
+            .map(|(server_name, server)| {
+                *ctx.route_cache
+                    .get(&(server_name.clone(), server.root.clone()))
+                    .unwrap()
+            })
+            .collect();
+        for (server_id, (_, server)) in server_ids.into_iter().zip(meta.language_server.iter()) {
+            if !should_start_server(meta, ctx, server_id) {
+                continue;
+            }
+            reconcile_server_settings(meta, ctx, server_id, server.settings.clone());
+        }
+    }
+}
+
+/// Recompute `server_id`'s fully-resolved initialization section and compare
+/// it against the value we last applied. Unchanged servers are left alone;
+/// servers that advertise `workspace/didChangeConfiguration` *and* haven't
+/// opted out via `disable_live_reconfiguration` get the new settings pushed
+/// live with no restart; everything else gets `settings` actually
+/// reassigned to `new_settings`, so the controller's existing
+/// restart-on-settings-change path picks up the genuine change. `settings`
+/// is deliberately left untouched in the other two cases, so unchanged
+/// servers aren't rewritten and live-reconfigured servers don't also
+/// trigger a restart.
+fn reconcile_server_settings(
+    meta: &EditorMeta,
+    ctx: &mut Context,
+    server_id: ServerId,
+    new_settings: Option<Value>,
+) {
+    let resolved = resolve_initialization_options(&[server_id], meta, ctx)
+        .pop()
+        .flatten();
+
+    let server = ctx.language_servers.get(&server_id).unwrap();
+    if json_values_equivalent(server.applied_settings.as_ref(), resolved.as_ref()) {
+        return;
+    }
+
+    let server_name = &server.name;
+    let disable_live_reconfiguration = server_configs(&ctx.config, meta)
+        .get(server_name)
+        .is_some_and(|cfg| cfg.disable_live_reconfiguration);
+
+    let supports_live_reconfiguration = server
+        .capabilities
+        .as_ref()
+        .and_then(|caps| caps.workspace.as_ref())
+        .and_then(|ws| ws.did_change_configuration.as_ref())
+        .is_some()
+        && !disable_live_reconfiguration;
+
+    ctx.language_servers.get_mut(&server_id).unwrap().applied_settings = resolved.clone();
+
+    if supports_live_reconfiguration {
+        ctx.notify::<DidChangeConfiguration>(
+            server_id,
+            DidChangeConfigurationParams {
+                settings: resolved.unwrap_or(Value::Null),
+            },
+        );
+    } else {
+        ctx.language_servers.get_mut(&server_id).unwrap().settings = new_settings;
+    }
+}
+
+/// Structurally compare two optional JSON values, treating a missing value
+/// (`None`) the same as an explicit `null`. Inside objects no such
+/// normalization happens: an absent key and a key set to `null` compare as
+/// different.
+fn json_values_equivalent(old: Option<&Value>, new: Option<&Value>) -> bool {
+    fn normalize(value: Option<&Value>) -> &Value {
+        match value {
+            Some(value) => value,
+            None => &Value::Null,
         }
     }
+    normalize(old) == normalize(new)
 }
 
 /// User may override initialization options on per-language server basis
@@ -124,6 +419,76 @@ pub fn request_legacy_initialization_options_from_kakoune(
     }
 }
 
+/// Expand `${env:VAR}` and `${env:VAR:-default}` references in string scalars
+/// of a just-parsed setting value, recursing into arrays and tables so a
+/// single committed `lsp_config` can still pull machine-specific paths or
+/// secrets (e.g. `GOPATH`, token paths, SDK locations) out of the
+/// environment instead of hardcoding them per checkout.
+fn expand_env_vars(session: &SessionId, raw_key: &str, value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(expand_env_vars_in_str(session, raw_key, &s)),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| expand_env_vars(session, raw_key, item))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, expand_env_vars(session, raw_key, v)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn expand_env_vars_in_str(session: &SessionId, raw_key: &str, input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${env:") {
+        output.push_str(&rest[..start]);
+        let after_marker = &rest[start + "${env:".len()..];
+        let Some(end) = after_marker.find('}') else {
+            output.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let body = &after_marker[..end];
+        let (var_name, default) = match body.split_once(":-") {
+            Some((var_name, default)) => (var_name, Some(default)),
+            None => (body, None),
+        };
+        output.push_str(&resolve_env_var(session, raw_key, var_name, default));
+        rest = &after_marker[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Look up `var_name` in the process environment, trying the name as given
+/// and with `-`/`_` swapped, since Kakoune options and TOML keys tend to
+/// prefer dashes while shells only allow underscores.
+fn resolve_env_var(session: &SessionId, raw_key: &str, var_name: &str, default: Option<&str>) -> String {
+    let candidates = [
+        var_name.to_string(),
+        var_name.replace('-', "_"),
+        var_name.replace('_', "-"),
+    ];
+    for candidate in &candidates {
+        if let Ok(value) = std::env::var(candidate) {
+            return value;
+        }
+    }
+    if let Some(default) = default {
+        return default.to_string();
+    }
+    warn!(
+        session,
+        "Setting {:?} references ${{env:{}}}, but it is not set in the environment", raw_key, var_name
+    );
+    String::new()
+}
+
 fn insert_value<'a, 'b, P>(
     target: &'b mut serde_json::Map<String, Value>,
     mut path: P,
@@ -198,6 +563,8 @@ pub fn explode_str_to_str_map(
             }
         };
 
+        let value = expand_env_vars(session, raw_key, value);
+
         match insert_value(&mut settings, key_parts, local_key.into(), value) {
             Ok(_) => (),
             Err(e) => {
@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use lsp_types::ServerCapabilities;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Static, user-authored configuration for one language server, as parsed
+/// from `kak-lsp.toml`'s `[language_server.<name>]` table. Looked up by
+/// server name via `server_configs`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Settings sent verbatim (modulo `settings_section`) as the server's
+    /// `initializationOptions`/`workspace/didChangeConfiguration` payload.
+    pub settings: Option<Value>,
+    /// Key of `settings` whose value is the one actually used (see
+    /// `configured_section`); when unset, `settings` is not used at all.
+    pub settings_section: Option<String>,
+    /// Which LSP features (hover, completion, ...) this server participates
+    /// in; consulted by `has_feature`/`servers_for_feature`.
+    pub features: ServerFeatures,
+    /// If set, the server stays dormant (see `should_start_server`) until a
+    /// matching `settings_section` shows up along the static, dynamic, or
+    /// legacy config path.
+    pub requires_configuration: bool,
+    /// Opt out of pushing changed settings live via
+    /// `workspace/didChangeConfiguration` even when the server advertises
+    /// support for it, forcing a restart instead. See
+    /// `reconcile_server_settings`.
+    pub disable_live_reconfiguration: bool,
+}
+
+/// Runtime state for one spawned (or about-to-be-spawned) language server
+/// instance, keyed by `ServerId` in `ctx.language_servers`.
+#[derive(Clone, Debug, Default)]
+pub struct Server {
+    pub name: String,
+    /// Effective initialization settings last requested from Kakoune;
+    /// reassigned whenever a non-live-reconfigurable change is reconciled.
+    pub settings: Option<Value>,
+    /// Fully resolved settings last actually applied to the running server
+    /// (via `initialize` or a live `workspace/didChangeConfiguration` push),
+    /// used by `reconcile_server_settings` to detect no-op changes.
+    pub applied_settings: Option<Value>,
+    pub capabilities: Option<ServerCapabilities>,
+}
+
+/// Per-server feature gating, parsed alongside `settings`/`settings_section`
+/// in `server_configs`. A server with an empty `only` set participates in
+/// every feature unless explicitly `excluded`; a non-empty `only` set
+/// restricts it to just those features (still subject to `excluded`).
+///
+/// Lives on the per-server entry in `server_configs` as the `features`
+/// field, next to the existing `settings`/`settings_section` fields.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerFeatures {
+    pub only: HashSet<String>,
+    pub excluded: HashSet<String>,
+}